@@ -0,0 +1,140 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use sourmash::signature::Signature;
+use zip::ZipArchive;
+
+use crate::manifest;
+
+/// The name sourmash gives the manifest CSV at the root of a `.sig.zip`
+/// archive (the ZipStorage layout).
+const ZIP_MANIFEST_NAME: &str = "SOURMASH-MANIFEST.csv";
+
+thread_local! {
+    /// Each rayon worker thread keeps its own open `ZipArchive` per archive
+    /// path, opened lazily on first use and reused across every member it
+    /// reads afterwards. This avoids reopening the file or re-parsing the
+    /// central directory per signature, while still letting reads from
+    /// different threads proceed in parallel instead of serializing on a
+    /// shared lock.
+    static ZIP_CACHE: RefCell<HashMap<PathBuf, ZipArchive<File>>> = RefCell::new(HashMap::new());
+}
+
+/// Where a search signature actually lives: a plain file on disk, or a
+/// member of a `.sig.zip` archive (the ZipStorage layout sourmash writes
+/// for large reference collections).
+#[derive(Clone)]
+pub enum SigLocation {
+    Path(PathBuf),
+    Zip {
+        archive_path: PathBuf,
+        internal_path: String,
+    },
+}
+
+impl std::fmt::Debug for SigLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.display_location())
+    }
+}
+
+impl SigLocation {
+    /// The archive-relative location to record in the output, e.g.
+    /// `catalog.sig.zip/signatures/deadbeef.sig.gz` for a zip member.
+    pub fn display_location(&self) -> String {
+        match self {
+            SigLocation::Path(path) => path.display().to_string(),
+            SigLocation::Zip {
+                archive_path,
+                internal_path,
+                ..
+            } => format!("{}/{}", archive_path.display(), internal_path),
+        }
+    }
+
+    /// Load the `Signature` this location points to, transparently
+    /// gunzipping `.gz` members the same way `Signature::from_path` does
+    /// for on-disk `.sig.gz` files.
+    pub fn load(&self) -> Result<Signature, Box<dyn std::error::Error>> {
+        match self {
+            SigLocation::Path(path) => Signature::from_path(path)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("no signatures found in {:?}", path).into()),
+            SigLocation::Zip {
+                archive_path,
+                internal_path,
+            } => {
+                let raw = ZIP_CACHE.with(|cache| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+                    let mut cache = cache.borrow_mut();
+                    let zip = match cache.entry(archive_path.clone()) {
+                        std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+                        std::collections::hash_map::Entry::Vacant(e) => {
+                            e.insert(ZipArchive::new(File::open(archive_path)?)?)
+                        }
+                    };
+                    let mut entry = zip.by_name(internal_path)?;
+                    let mut raw = Vec::new();
+                    entry.read_to_end(&mut raw)?;
+                    Ok(raw)
+                })?;
+
+                let (mut reader, _format) = niffler::get_reader(Box::new(&raw[..]))?;
+                let mut contents = Vec::new();
+                reader.read_to_end(&mut contents)?;
+
+                Signature::from_reader(&contents[..])?
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| format!("no signatures found in {}", self.display_location()).into())
+            }
+        }
+    }
+}
+
+/// Expand a siglist entry into one or more search-signature locations.
+/// Plain `.sig` paths pass through unchanged. `.zip` archives are opened
+/// once here: if they carry a `SOURMASH-MANIFEST.csv`, its
+/// `internal_location` column is used to enumerate members (as the request
+/// asks); otherwise we fall back to listing `.sig`/`.sig.gz` entries
+/// directly. The archive is then reopened lazily, once per worker thread,
+/// via `ZIP_CACHE` as its members are actually read, so threads don't
+/// contend on a shared handle.
+pub fn expand_entry(path: &Path) -> Result<Vec<SigLocation>, Box<dyn std::error::Error>> {
+    if path.extension().and_then(|e| e.to_str()) != Some("zip") {
+        return Ok(vec![SigLocation::Path(path.to_path_buf())]);
+    }
+
+    let mut zip = ZipArchive::new(File::open(path)?)?;
+
+    let all_names: Vec<String> = (0..zip.len())
+        .map(|i| zip.by_index(i).map(|f| f.name().to_string()))
+        .collect::<Result<_, _>>()?;
+
+    let members: Vec<String> = if all_names.iter().any(|name| name == ZIP_MANIFEST_NAME) {
+        let records = {
+            let entry = zip.by_name(ZIP_MANIFEST_NAME)?;
+            manifest::parse_manifest_lines(BufReader::new(entry).lines())?
+        };
+        records
+            .into_iter()
+            .map(|record| record.internal_location)
+            .collect()
+    } else {
+        all_names
+            .into_iter()
+            .filter(|name| name.ends_with(".sig") || name.ends_with(".sig.gz"))
+            .collect()
+    };
+
+    Ok(members
+        .into_iter()
+        .map(|internal_path| SigLocation::Zip {
+            archive_path: path.to_path_buf(),
+            internal_path,
+        })
+        .collect())
+}
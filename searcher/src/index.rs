@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use sourmash::signature::SigsTrait;
+use sourmash::sketch::minhash::KmerMinHash;
+
+/// A reverse index from hash value to the queries that contain it.
+///
+/// Building this once up front turns the O(queries * signatures) pairwise
+/// `count_common` loop into a single pass over each search sketch's hashes:
+/// for every hash we look up which queries care about it and bump their
+/// counters, instead of intersecting the whole search sketch against every
+/// query in turn. This is the main win when there are thousands of queries.
+pub struct InvertedIndex {
+    hash_to_queries: HashMap<u64, Vec<u32>>,
+    query_sizes: Vec<usize>,
+}
+
+impl InvertedIndex {
+    /// Build an index over `queries`, which must already be downsampled to
+    /// the search template's scaled factor.
+    pub fn new(queries: &[(String, KmerMinHash)]) -> Self {
+        let mut hash_to_queries: HashMap<u64, Vec<u32>> = HashMap::new();
+        let mut query_sizes = Vec::with_capacity(queries.len());
+
+        for (idx, (_, mh)) in queries.iter().enumerate() {
+            query_sizes.push(mh.size());
+            for hash in mh.mins() {
+                hash_to_queries.entry(hash).or_default().push(idx as u32);
+            }
+        }
+
+        InvertedIndex {
+            hash_to_queries,
+            query_sizes,
+        }
+    }
+
+    pub fn n_queries(&self) -> usize {
+        self.query_sizes.len()
+    }
+
+    pub fn query_size(&self, idx: usize) -> usize {
+        self.query_sizes[idx]
+    }
+
+    /// For every query in the index, count how many hashes it shares with
+    /// `search_mh`. Runs in a single pass over `search_mh`'s hashes.
+    pub fn count_common(&self, search_mh: &KmerMinHash) -> Vec<u32> {
+        let mut counts = vec![0u32; self.n_queries()];
+        for hash in search_mh.mins() {
+            if let Some(idxs) = self.hash_to_queries.get(&hash) {
+                for &idx in idxs {
+                    counts[idx as usize] += 1;
+                }
+            }
+        }
+        counts
+    }
+}
@@ -7,9 +7,17 @@ use clap::Parser;
 use log::{error, info};
 use rayon::prelude::*;
 use sourmash::signature::{Signature, SigsTrait};
-use sourmash::sketch::minhash::{max_hash_for_scaled, KmerMinHash};
+use sourmash::sketch::minhash::{max_hash_for_scaled, HashFunctions, KmerMinHash};
 use sourmash::sketch::Sketch;
 
+mod archive;
+mod index;
+mod manifest;
+
+use archive::SigLocation;
+use index::InvertedIndex;
+use manifest::Picklist;
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Cli {
@@ -36,6 +44,71 @@ struct Cli {
     /// The path for output
     #[clap(parse(from_os_str), short, long)]
     output: Option<PathBuf>,
+
+    /// Build an inverted index over the queries instead of comparing them
+    /// pairwise against each search signature. Wins when there are many
+    /// (thousands of) queries, since each search sketch is only scanned once.
+    #[clap(long)]
+    index: bool,
+
+    /// Filter the loaded signatures to those matching a column of values,
+    /// given as FILE:COL:FIELD: FILE is a CSV, COL is the column in FILE
+    /// holding the accepted values, and FIELD is the manifest column to
+    /// match them against (md5, name, or ident).
+    #[clap(long)]
+    picklist: Option<String>,
+
+    /// Molecule type of the sketches being compared.
+    #[clap(arg_enum, long, default_value = "dna")]
+    moltype: MolType,
+
+    /// Emit extra similarity columns (Jaccard, max-containment, intersection
+    /// size, and both sketch sizes) alongside containment.
+    #[clap(long)]
+    all_metrics: bool,
+}
+
+#[derive(clap::ArgEnum, Clone, Debug)]
+enum MolType {
+    Dna,
+    Protein,
+    Dayhoff,
+    Hp,
+}
+
+impl From<MolType> for HashFunctions {
+    fn from(moltype: MolType) -> HashFunctions {
+        match moltype {
+            MolType::Dna => HashFunctions::Murmur64Dna,
+            MolType::Protein => HashFunctions::Murmur64Protein,
+            MolType::Dayhoff => HashFunctions::Murmur64Dayhoff,
+            MolType::Hp => HashFunctions::Murmur64Hp,
+        }
+    }
+}
+
+/// A single query/match pair that cleared the containment threshold.
+struct SearchResult {
+    query: String,
+    location: String,
+    intersection: u64,
+    query_size: u64,
+    match_size: u64,
+}
+
+impl SearchResult {
+    fn containment(&self) -> f64 {
+        self.intersection as f64 / self.query_size as f64
+    }
+
+    fn jaccard(&self) -> f64 {
+        let union = self.query_size + self.match_size - self.intersection;
+        self.intersection as f64 / union as f64
+    }
+
+    fn max_containment(&self) -> f64 {
+        self.intersection as f64 / self.query_size.min(self.match_size) as f64
+    }
 }
 
 fn check_compatible_downsample(
@@ -90,6 +163,52 @@ fn prepare_query(search_sig: &Signature, template: &Sketch) -> Option<KmerMinHas
     search_mh
 }
 
+/// Load the paths referenced by a querylist/siglist argument. `list_path`
+/// may be either a plain newline-delimited list of signature paths, or a
+/// sourmash manifest CSV (in which case entries whose manifest-recorded
+/// ksize/moltype/scaled can't match `template_mh` are skipped before ever
+/// opening the underlying signature file).
+///
+/// `picklist`, when given, only ever applies to a manifest CSV: a plain
+/// path list has no columns to match it against, so passing one alongside
+/// a plain list is an error rather than a silent no-op.
+fn load_sig_paths(
+    list_path: &Path,
+    template_mh: &KmerMinHash,
+    picklist: Option<&Picklist>,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    if manifest::is_manifest_csv(list_path)? {
+        let records = manifest::load_manifest(list_path)?;
+        let manifest_dir = list_path.parent().unwrap_or_else(|| Path::new(""));
+        Ok(records
+            .into_iter()
+            .filter(|record| record.compatible_with(template_mh))
+            .filter(|record| picklist.map_or(true, |p| p.matches(record)))
+            .map(|record| record.path(manifest_dir))
+            .collect())
+    } else {
+        if picklist.is_some() {
+            return Err(format!(
+                "--picklist requires a manifest CSV, but {:?} is a plain path list",
+                list_path
+            )
+            .into());
+        }
+        let file = BufReader::new(File::open(list_path)?);
+        Ok(file
+            .lines()
+            .filter_map(|line| {
+                let line = line.unwrap();
+                if line.is_empty() {
+                    None
+                } else {
+                    Some(PathBuf::from(line))
+                }
+            })
+            .collect())
+    }
+}
+
 fn search<P: AsRef<Path>>(
     querylist: P,
     siglist: P,
@@ -97,32 +216,25 @@ fn search<P: AsRef<Path>>(
     ksize: u8,
     scaled: usize,
     output: Option<P>,
+    use_index: bool,
+    picklist: Option<&Picklist>,
+    hash_function: HashFunctions,
+    all_metrics: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     info!("Loading queries");
 
-    let querylist_file = BufReader::new(File::open(querylist)?);
-
     let max_hash = max_hash_for_scaled(scaled as u64);
     let template_mh = KmerMinHash::builder()
         .num(0u32)
         .ksize(ksize as u32)
+        .hash_function(hash_function)
         .max_hash(max_hash)
         .build();
-    let template = Sketch::MinHash(template_mh);
-
-    let queries: Vec<(String, KmerMinHash)> = querylist_file
-        .lines()
-        .filter_map(|line| {
-            let line = line.unwrap();
-            if !line.is_empty() {
-                // skip empty lines
-                let mut path = PathBuf::new();
-                path.push(line);
-                Some(path)
-            } else {
-                None
-            }
-        })
+    let template = Sketch::MinHash(template_mh.clone());
+
+    // --picklist only ever filters the search siglist, not the queries.
+    let queries: Vec<(String, KmerMinHash)> = load_sig_paths(querylist.as_ref(), &template_mh, None)?
+        .into_iter()
         .filter_map(|query| {
             let query_sig = Signature::from_path(query).unwrap();
 
@@ -144,22 +256,22 @@ fn search<P: AsRef<Path>>(
     info!("Loaded {} query signatures", queries.len());
 
     info!("Loading siglist");
-    let siglist_file = BufReader::new(File::open(siglist)?);
-    let search_sigs: Vec<PathBuf> = siglist_file
-        .lines()
-        .filter_map(|line| {
-            let line = line.unwrap();
-            if !line.is_empty() {
-                let mut path = PathBuf::new();
-                path.push(line);
-                Some(path)
-            } else {
-                None
-            }
+    let search_sigs: Vec<SigLocation> = load_sig_paths(siglist.as_ref(), &template_mh, picklist)?
+        .iter()
+        .flat_map(|path| {
+            archive::expand_entry(path)
+                .unwrap_or_else(|e| panic!("Error reading {:?}: {}", path, e))
         })
         .collect();
     info!("Loaded {} sig paths in siglist", search_sigs.len());
 
+    let inverted_index = if use_index {
+        info!("Building inverted index over {} queries", queries.len());
+        Some(InvertedIndex::new(&queries))
+    } else {
+        None
+    };
+
     let processed_sigs = AtomicUsize::new(0);
 
     let (send, recv) = std::sync::mpsc::sync_channel(rayon::current_num_threads());
@@ -171,37 +283,97 @@ fn search<P: AsRef<Path>>(
     };
     let thrd = std::thread::spawn(move || {
         let mut writer = BufWriter::new(out);
-        writeln!(&mut writer, "query,Run,containment").unwrap();
-        for (query, m, containment) in recv.into_iter() {
-            writeln!(&mut writer, "'{}','{}',{}", query, m, containment).unwrap();
+        if all_metrics {
+            writeln!(
+                &mut writer,
+                "query,Run,containment,jaccard,max_containment,intersection,query_size,match_size"
+            )
+            .unwrap();
+        } else {
+            writeln!(&mut writer, "query,Run,containment").unwrap();
+        }
+        for result in recv.into_iter() {
+            if all_metrics {
+                writeln!(
+                    &mut writer,
+                    "'{}','{}',{},{},{},{},{},{}",
+                    result.query,
+                    result.location,
+                    result.containment(),
+                    result.jaccard(),
+                    result.max_containment(),
+                    result.intersection,
+                    result.query_size,
+                    result.match_size,
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    &mut writer,
+                    "'{}','{}',{}",
+                    result.query,
+                    result.location,
+                    result.containment()
+                )
+                .unwrap();
+            }
         }
     });
 
     let send = search_sigs
         .par_iter()
-        .filter_map(|filename| {
+        .filter_map(|location| {
             let i = processed_sigs.fetch_add(1, Ordering::SeqCst);
             if i % 1000 == 0 {
                 info!("Processed {} search sigs", i);
             }
 
-            let mut search_mh = None;
-            let search_sig = &Signature::from_path(&filename)
-                .unwrap_or_else(|_| panic!("Error processing {:?}", filename))[0];
+            let search_sig = location
+                .load()
+                .unwrap_or_else(|e| panic!("Error processing {:?}: {}", location, e));
 
-            if let Some(mh) = prepare_query(search_sig, &template) {
-                search_mh = Some(mh);
-            }
-            let search_mh = search_mh.unwrap();
+            // Skip members whose sketch can't be matched against the
+            // template (e.g. a mixed-ksize/moltype catalog) rather than
+            // panicking the whole search over one incompatible signature.
+            let search_mh = match prepare_query(&search_sig, &template) {
+                Some(mh) => mh,
+                None => return None,
+            };
+            let match_size = search_mh.size() as u64;
 
-            let match_fn = filename.clone().into_os_string().into_string().unwrap();
+            let match_fn = location.display_location();
             let mut results = vec![];
 
-            for (name, query) in &queries {
-                let containment =
-                    query.count_common(&search_mh, false).unwrap() as f64 / query.size() as f64;
-                if containment > threshold {
-                    results.push((name.clone(), match_fn.clone(), containment))
+            if let Some(index) = &inverted_index {
+                let counts = index.count_common(&search_mh);
+                for (i, (name, _)) in queries.iter().enumerate() {
+                    let intersection = counts[i] as u64;
+                    let query_size = index.query_size(i) as u64;
+                    let containment = intersection as f64 / query_size as f64;
+                    if containment > threshold {
+                        results.push(SearchResult {
+                            query: name.clone(),
+                            location: match_fn.clone(),
+                            intersection,
+                            query_size,
+                            match_size,
+                        })
+                    }
+                }
+            } else {
+                for (name, query) in &queries {
+                    let intersection = query.count_common(&search_mh, false).unwrap() as u64;
+                    let query_size = query.size() as u64;
+                    let containment = intersection as f64 / query_size as f64;
+                    if containment > threshold {
+                        results.push(SearchResult {
+                            query: name.clone(),
+                            location: match_fn.clone(),
+                            intersection,
+                            query_size,
+                            match_size,
+                        })
+                    }
                 }
             }
             if results.is_empty() {
@@ -231,6 +403,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let opts = Cli::parse();
 
+    let picklist = opts
+        .picklist
+        .as_deref()
+        .map(Picklist::parse)
+        .transpose()?;
+
     search(
         opts.querylist,
         opts.siglist,
@@ -238,6 +416,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         opts.ksize,
         opts.scaled,
         opts.output,
+        opts.index,
+        picklist.as_ref(),
+        opts.moltype.into(),
+        opts.all_metrics,
     )?;
 
     Ok(())
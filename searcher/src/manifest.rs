@@ -0,0 +1,214 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use sourmash::sketch::minhash::KmerMinHash;
+use sourmash::signature::SigsTrait;
+
+/// The header sourmash writes as the first line of a manifest CSV, e.g.
+/// `# SOURMASH-MANIFEST-VERSION: 1.0`.
+const MANIFEST_HEADER_PREFIX: &str = "# SOURMASH-MANIFEST-VERSION";
+
+/// A single row of a sourmash manifest CSV, as produced by `sourmash sig manifest`.
+#[derive(Debug, Clone)]
+pub struct ManifestRecord {
+    pub internal_location: String,
+    pub md5: String,
+    pub md5short: String,
+    pub ksize: u32,
+    pub moltype: String,
+    pub num: u32,
+    pub scaled: u64,
+    pub n_hashes: usize,
+    pub with_abundance: bool,
+    pub name: String,
+    pub filename: String,
+}
+
+impl ManifestRecord {
+    /// Whether this record could plausibly hold a sketch compatible with
+    /// `template`, judging only by the manifest's own metadata (no need to
+    /// open the signature file to find out).
+    pub fn compatible_with(&self, template: &KmerMinHash) -> bool {
+        // num sketches (scaled == 0) aren't downsamplable to a scaled
+        // template and would otherwise slip through the scaled check below
+        // since 0 <= anything.
+        if self.scaled == 0 {
+            return false;
+        }
+        if self.ksize != template.ksize() {
+            return false;
+        }
+        if !self
+            .moltype
+            .eq_ignore_ascii_case(&template.hash_function().to_string())
+        {
+            return false;
+        }
+        // the template's scaled must be reachable by downsampling, i.e.
+        // the record's scaled must be no coarser than the template's.
+        self.scaled <= template.scaled()
+    }
+
+    /// Path to the underlying signature file. `internal_location` is
+    /// recorded relative to the manifest itself, not the process's current
+    /// directory, so it must be resolved against the manifest's own
+    /// directory (`manifest_dir`).
+    pub fn path(&self, manifest_dir: &Path) -> PathBuf {
+        manifest_dir.join(&self.internal_location)
+    }
+}
+
+/// Split a single manifest CSV line into fields, honoring double-quoted
+/// fields that may themselves contain commas.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                fields.push(field.clone());
+                field.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Check whether `path` looks like a sourmash manifest CSV rather than a
+/// plain newline-delimited list of signature paths.
+pub fn is_manifest_csv<P: AsRef<Path>>(path: P) -> std::io::Result<bool> {
+    let file = File::open(path)?;
+    let mut lines = BufReader::new(file).lines();
+    match lines.next() {
+        Some(Ok(first)) => Ok(first.starts_with(MANIFEST_HEADER_PREFIX)),
+        _ => Ok(false),
+    }
+}
+
+/// Parse a sourmash manifest CSV into its records.
+///
+/// The first line is the `# SOURMASH-MANIFEST-VERSION` comment and the
+/// second is the column header; both are skipped.
+pub fn load_manifest<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<ManifestRecord>> {
+    let file = File::open(path)?;
+    parse_manifest_lines(BufReader::new(file).lines())
+}
+
+/// Parse a manifest CSV from any source of lines, e.g. a plain file or an
+/// entry inside a zip archive.
+pub fn parse_manifest_lines<I>(mut lines: I) -> std::io::Result<Vec<ManifestRecord>>
+where
+    I: Iterator<Item = std::io::Result<String>>,
+{
+    lines.next(); // "# SOURMASH-MANIFEST-VERSION: 1.0"
+    let header = match lines.next() {
+        Some(line) => line?,
+        None => return Ok(Vec::new()),
+    };
+    let columns = split_csv_line(&header);
+    let col_idx = |name: &str| columns.iter().position(|c| c == name);
+
+    let internal_location_idx = col_idx("internal_location").unwrap_or(0);
+    let md5_idx = col_idx("md5").unwrap_or(1);
+    let md5short_idx = col_idx("md5short").unwrap_or(2);
+    let ksize_idx = col_idx("ksize").unwrap_or(3);
+    let moltype_idx = col_idx("moltype").unwrap_or(4);
+    let num_idx = col_idx("num").unwrap_or(5);
+    let scaled_idx = col_idx("scaled").unwrap_or(6);
+    let n_hashes_idx = col_idx("n_hashes").unwrap_or(7);
+    let with_abundance_idx = col_idx("with_abundance").unwrap_or(8);
+    let name_idx = col_idx("name").unwrap_or(9);
+    let filename_idx = col_idx("filename").unwrap_or(10);
+
+    let mut records = Vec::new();
+    for line in lines {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let fields = split_csv_line(&line);
+        let get = |idx: usize| fields.get(idx).cloned().unwrap_or_default();
+
+        records.push(ManifestRecord {
+            internal_location: get(internal_location_idx),
+            md5: get(md5_idx),
+            md5short: get(md5short_idx),
+            ksize: get(ksize_idx).parse().unwrap_or_default(),
+            moltype: get(moltype_idx),
+            num: get(num_idx).parse().unwrap_or_default(),
+            scaled: get(scaled_idx).parse().unwrap_or_default(),
+            n_hashes: get(n_hashes_idx).parse().unwrap_or_default(),
+            with_abundance: get(with_abundance_idx) == "1" || get(with_abundance_idx) == "True",
+            name: get(name_idx),
+            filename: get(filename_idx),
+        });
+    }
+
+    Ok(records)
+}
+
+/// A `--picklist FILE:COL:FIELD` filter: `FILE` is a CSV of accepted values,
+/// `COL` is the column in `FILE` to read them from, and `FIELD` is the
+/// manifest column (`md5`, `name`, or `ident`) to match them against.
+pub struct Picklist {
+    pub field: String,
+    values: HashSet<String>,
+}
+
+impl Picklist {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = spec.splitn(3, ':').collect();
+        let (file, col, field) = match parts.as_slice() {
+            [file, col, field] => (*file, *col, *field),
+            _ => return Err(format!("expected FILE:COL:FIELD, got {:?}", spec)),
+        };
+
+        let f = File::open(file).map_err(|e| format!("can't open picklist {}: {}", file, e))?;
+        let mut lines = BufReader::new(f).lines();
+
+        let header = lines
+            .next()
+            .ok_or_else(|| format!("empty picklist file {}", file))?
+            .map_err(|e| e.to_string())?;
+        let columns = split_csv_line(&header);
+        let col_idx = columns
+            .iter()
+            .position(|c| c == col)
+            .ok_or_else(|| format!("column {} not found in {}", col, file))?;
+
+        let mut values = HashSet::new();
+        for line in lines {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(value) = split_csv_line(&line).get(col_idx) {
+                values.insert(value.clone());
+            }
+        }
+
+        Ok(Picklist {
+            field: field.to_string(),
+            values,
+        })
+    }
+
+    /// Whether `record` matches one of the picklist's accepted values.
+    pub fn matches(&self, record: &ManifestRecord) -> bool {
+        let actual = match self.field.as_str() {
+            "md5" => record.md5.as_str(),
+            "name" => record.name.as_str(),
+            "ident" => record.name.split_whitespace().next().unwrap_or(""),
+            _ => return false,
+        };
+        self.values.contains(actual)
+    }
+}
@@ -110,6 +110,275 @@ fn search_catalog_empty_line() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn search_indexed_matches_pairwise() -> Result<(), Box<dyn std::error::Error>> {
+    // Multiple queries, so the comparison actually exercises the inverted
+    // index's per-query bookkeeping (index.rs) rather than the single-query
+    // case, where a bug in per-query accounting could go unnoticed.
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+    writeln!(queries, "tests/data/genome-s11.fa.gz.sig")?;
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "tests/data/genome-s10.fa.gz.sig")?;
+    writeln!(catalog, "tests/data/genome-s11.fa.gz.sig")?;
+    writeln!(catalog, "tests/data/genome-s12.fa.gz.sig")?;
+
+    let mut pairwise = Command::cargo_bin("searcher")?;
+    let pairwise_out = pairwise
+        .args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains(
+            "../genome-s10.fa.gz','tests/data/genome-s10.fa.gz.sig',1",
+        ))
+        .get_output()
+        .stdout
+        .clone();
+
+    let mut indexed = Command::cargo_bin("searcher")?;
+    let indexed_out = indexed
+        .args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg("--index")
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains("query,Run,containment"))
+        .get_output()
+        .stdout
+        .clone();
+
+    // The indexed and pairwise paths compute containment differently (a
+    // single pass over a hash map vs. repeated `count_common` calls), but
+    // must agree on which query/match pairs clear the threshold and at
+    // what containment. Row order isn't guaranteed (results race through
+    // a channel from parallel workers), so compare as sorted line sets.
+    let sorted_lines = |out: &[u8]| {
+        let mut lines: Vec<String> = String::from_utf8(out.to_vec())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        lines.sort();
+        lines
+    };
+
+    assert_eq!(sorted_lines(&pairwise_out), sorted_lines(&indexed_out));
+
+    Ok(())
+}
+
+#[test]
+fn search_siglist_manifest() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+
+    // internal_location is resolved against the manifest's own directory,
+    // not the process cwd, so it must be absolute here since the manifest
+    // itself lives in a tempdir unrelated to the crate root.
+    let s10 = format!("{}/tests/data/genome-s10.fa.gz.sig", env!("CARGO_MANIFEST_DIR"));
+    let s11 = format!("{}/tests/data/genome-s11.fa.gz.sig", env!("CARGO_MANIFEST_DIR"));
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "# SOURMASH-MANIFEST-VERSION: 1.0")?;
+    writeln!(catalog, "internal_location,md5,md5short,ksize,moltype,num,scaled,n_hashes,with_abundance,name,filename")?;
+    writeln!(catalog, "{},deadbeef,deadbeef,31,DNA,0,10000,500,0,genome-s10,genome-s10.fa.gz", s10)?;
+    writeln!(catalog, "{},cafebabe,cafebabe,31,DNA,0,10000,500,0,genome-s11,genome-s11.fa.gz", s11)?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains("query,Run,containment"))
+        .stdout(contains(format!("','{}',1", s10)));
+
+    Ok(())
+}
+
+#[test]
+fn search_picklist_filters_by_name() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+
+    let s10 = format!("{}/tests/data/genome-s10.fa.gz.sig", env!("CARGO_MANIFEST_DIR"));
+    let s11 = format!("{}/tests/data/genome-s11.fa.gz.sig", env!("CARGO_MANIFEST_DIR"));
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "# SOURMASH-MANIFEST-VERSION: 1.0")?;
+    writeln!(catalog, "internal_location,md5,md5short,ksize,moltype,num,scaled,n_hashes,with_abundance,name,filename")?;
+    writeln!(catalog, "{},deadbeef,deadbeef,31,DNA,0,10000,500,0,genome-s10,genome-s10.fa.gz", s10)?;
+    writeln!(catalog, "{},cafebabe,cafebabe,31,DNA,0,10000,500,0,genome-s11,genome-s11.fa.gz", s11)?;
+
+    let mut picklist = NamedTempFile::new()?;
+    writeln!(picklist, "name")?;
+    writeln!(picklist, "genome-s10")?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg("--picklist")
+        .arg(format!("{}:name:name", picklist.path().display()))
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains("query,Run,containment"))
+        .stdout(contains(format!("','{}',1", s10)))
+        .stdout(contains(s11).not());
+
+    Ok(())
+}
+
+#[test]
+fn search_picklist_requires_manifest_siglist() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "tests/data/genome-s10.fa.gz.sig")?;
+
+    let mut picklist = NamedTempFile::new()?;
+    writeln!(picklist, "name")?;
+    writeln!(picklist, "genome-s10")?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg("--picklist")
+        .arg(format!("{}:name:name", picklist.path().display()))
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn search_picklist_does_not_filter_queries() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    // The querylist is a plain path list, which --picklist cannot be applied
+    // to: if --picklist were (mis-)applied to the querylist too, loading the
+    // queries would fail with an error instead of searching.
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+
+    let s10 = format!("{}/tests/data/genome-s10.fa.gz.sig", env!("CARGO_MANIFEST_DIR"));
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "# SOURMASH-MANIFEST-VERSION: 1.0")?;
+    writeln!(catalog, "internal_location,md5,md5short,ksize,moltype,num,scaled,n_hashes,with_abundance,name,filename")?;
+    writeln!(catalog, "{},deadbeef,deadbeef,31,DNA,0,10000,500,0,genome-s10,genome-s10.fa.gz", s10)?;
+
+    let mut picklist = NamedTempFile::new()?;
+    writeln!(picklist, "name")?;
+    writeln!(picklist, "genome-s10")?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg("--picklist")
+        .arg(format!("{}:name:name", picklist.path().display()))
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains(format!("','{}',1", s10)));
+
+    Ok(())
+}
+
+#[test]
+fn search_siglist_zip_archive() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "tests/data/catalog.sig.zip")?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains("query,Run,containment"))
+        .stdout(contains("tests/data/catalog.sig.zip/"));
+
+    Ok(())
+}
+
+#[test]
+fn search_protein_moltype() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/protein-s10.sig")?;
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "tests/data/protein-s10.sig")?;
+    writeln!(catalog, "tests/data/protein-s11.sig")?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "10"])
+        .args(&["--scaled", "100"])
+        .args(&["--moltype", "protein"])
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains("query,Run,containment"));
+
+    Ok(())
+}
+
+#[test]
+fn search_all_metrics() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("searcher")?;
+
+    let mut queries = NamedTempFile::new()?;
+    writeln!(queries, "tests/data/genome-s10.fa.gz.sig")?;
+
+    let mut catalog = NamedTempFile::new()?;
+    writeln!(catalog, "tests/data/genome-s10.fa.gz.sig")?;
+    writeln!(catalog, "tests/data/genome-s11.fa.gz.sig")?;
+    writeln!(catalog, "tests/data/genome-s12.fa.gz.sig")?;
+
+    cmd.args(&["--threshold", "0"])
+        .args(&["-k", "31"])
+        .args(&["--scaled", "10000"])
+        .arg("--all-metrics")
+        .arg(queries.path())
+        .arg(catalog.path())
+        .assert()
+        .success()
+        .stdout(contains(
+            "query,Run,containment,jaccard,max_containment,intersection,query_size,match_size",
+        ));
+
+    Ok(())
+}
+
 #[test]
 fn search_queries_empty_line() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = Command::cargo_bin("searcher")?;